@@ -0,0 +1,24 @@
+//! Constants used across the kernel.
+#![allow(unused)]
+
+/// User stack size (bytes)
+pub const USER_STACK_SIZE: usize = 4096 * 2;
+/// Kernel stack size (bytes)
+pub const KERNEL_STACK_SIZE: usize = 4096 * 2;
+/// Kernel heap size (bytes)
+pub const KERNEL_HEAP_SIZE: usize = 0x30_0000;
+/// End of usable physical memory
+pub const MEMORY_END: usize = 0x8080_0000;
+/// Page size (bytes)
+pub const PAGE_SIZE: usize = 0x1000;
+/// Page size in bits
+pub const PAGE_SIZE_BITS: usize = 0xc;
+/// The number of syscalls tracked per task
+pub const MAX_SYSCALL_NUM: usize = 500;
+/// Maximum number of harts (cores) the kernel supports; sizes the per-hart
+/// `Processor` array.
+pub const MAX_HARTS: usize = 4;
+/// Highest virtual page, shared by every address space for the trampoline.
+pub const TRAMPOLINE: usize = usize::MAX - PAGE_SIZE + 1;
+/// Virtual address of the trap context, just below the trampoline.
+pub const TRAP_CONTEXT_BASE: usize = TRAMPOLINE - PAGE_SIZE;
@@ -0,0 +1,506 @@
+//! Implementation of [`MapArea`] and [`MemorySet`].
+//!
+//! Non-overlapping regions are kept in an address-sorted balanced tree keyed by
+//! their start VPN, mirroring the `mm_struct` / `vm_area_struct` design: overlap
+//! checks and the covering-region lookup of a partial unmap are O(log n) rather
+//! than linear scans over a `Vec`; splitting a region still walks its pages.
+use super::{frame_alloc, FrameTracker};
+use super::{PTEFlags, PageTable, PageTableEntry};
+use super::{PhysAddr, PhysPageNum, VirtAddr, VirtPageNum};
+use super::{StepByOne, VPNRange};
+use crate::config::{MEMORY_END, PAGE_SIZE, TRAMPOLINE, TRAP_CONTEXT_BASE, USER_STACK_SIZE};
+use crate::sync::UPSafeCell;
+use alloc::collections::BTreeMap;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use lazy_static::*;
+use riscv::register::satp;
+
+extern "C" {
+    fn stext();
+    fn etext();
+    fn srodata();
+    fn erodata();
+    fn sdata();
+    fn edata();
+    fn sbss_with_stack();
+    fn ebss();
+    fn ekernel();
+    fn strampoline();
+}
+
+lazy_static! {
+    /// The kernel's own address space.
+    pub static ref KERNEL_SPACE: Arc<UPSafeCell<MemorySet>> =
+        Arc::new(unsafe { UPSafeCell::new(MemorySet::new_kernel()) });
+}
+
+/// The kernel's satp token.
+pub fn kernel_token() -> usize {
+    KERNEL_SPACE.exclusive_access().token()
+}
+
+/// An address space: a page table plus the regions mapped into it, held in an
+/// address-sorted tree keyed by each region's start VPN.
+pub struct MemorySet {
+    page_table: PageTable,
+    areas: BTreeMap<VirtPageNum, MapArea>,
+}
+
+impl MemorySet {
+    /// Create a new empty address space.
+    pub fn new_bare() -> Self {
+        Self {
+            page_table: PageTable::new(),
+            areas: BTreeMap::new(),
+        }
+    }
+    /// Get the satp token of this address space.
+    pub fn token(&self) -> usize {
+        self.page_table.token()
+    }
+
+    /// True when `[start_vpn, end_vpn)` overlaps an existing region. O(log n):
+    /// only the region immediately before `start_vpn` and the first region at or
+    /// after it can possibly overlap a non-overlapping tree.
+    fn overlaps(&self, start_vpn: VirtPageNum, end_vpn: VirtPageNum) -> bool {
+        if let Some((_, area)) = self.areas.range(..start_vpn).next_back() {
+            if area.vpn_range.get_end() > start_vpn {
+                return true;
+            }
+        }
+        if let Some((&s, _)) = self.areas.range(start_vpn..).next() {
+            if s < end_vpn {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Insert an already-mapped region into the tree (keyed by its start VPN).
+    fn record_area(&mut self, area: MapArea) {
+        self.areas.insert(area.vpn_range.get_start(), area);
+    }
+
+    /// Map a framed region, allocating all its frames now. Returns -1 if it
+    /// overlaps an existing region.
+    pub fn insert_framed_area(
+        &mut self,
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        permission: MapPermission,
+    ) -> isize {
+        let start_vpn = start_va.floor();
+        let end_vpn = end_va.ceil();
+        if self.overlaps(start_vpn, end_vpn) {
+            return -1;
+        }
+        let mut map_area = MapArea::new(start_va, end_va, MapType::Framed, permission);
+        map_area.map(&mut self.page_table);
+        self.record_area(map_area);
+        0
+    }
+
+    /// Reserve a framed region without allocating any frames: the [`MapArea`] is
+    /// recorded so its permission is known, but no PTEs are made present. The
+    /// pages are faulted in lazily by [`MemorySet::fault_in`]. Returns -1 on
+    /// overlap, like [`MemorySet::insert_framed_area`].
+    pub fn reserve_framed_area(
+        &mut self,
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        permission: MapPermission,
+    ) -> isize {
+        let start_vpn = start_va.floor();
+        let end_vpn = end_va.ceil();
+        if self.overlaps(start_vpn, end_vpn) {
+            return -1;
+        }
+        // deliberately skip map(): no frames allocated, no present PTEs yet.
+        let map_area = MapArea::new(start_va, end_va, MapType::Framed, permission);
+        self.record_area(map_area);
+        0
+    }
+
+    /// Demand-page the address `va`. If it falls inside a reserved region whose
+    /// page is not yet present, allocate one frame, map it with the region's
+    /// permission, zero it and return true so the faulting instruction can be
+    /// retried. If `va` is in no reserved region (or is already present), return
+    /// false so the caller can kill the task.
+    pub fn fault_in(&mut self, va: VirtAddr) -> bool {
+        let vpn = va.floor();
+        // covering region: greatest start <= vpn whose end is past vpn
+        let start = match self.areas.range(..=vpn).next_back() {
+            Some((&s, area)) if area.vpn_range.get_end() > vpn => s,
+            _ => return false,
+        };
+        let area = self.areas.get_mut(&start).unwrap();
+        if area.data_frames.contains_key(&vpn) {
+            return false;
+        }
+        area.map_one(&mut self.page_table, vpn);
+        // zero the freshly allocated frame
+        self.page_table
+            .translate(vpn)
+            .unwrap()
+            .ppn()
+            .get_bytes_array()
+            .fill(0);
+        true
+    }
+
+    /// Unmap `[range]`. Locate the region covering it; when the request lies
+    /// strictly inside the region, split it into up to two surviving regions and
+    /// free only the pages in between. Returns -1 if no region covers the range.
+    pub fn split_and_unmap(&mut self, range: VPNRange) -> isize {
+        let req_start = range.get_start();
+        let req_end = range.get_end();
+        // covering region: the one with the greatest start <= req_start
+        let start = match self.areas.range(..=req_start).next_back() {
+            Some((&s, area)) if area.vpn_range.get_end() >= req_end => s,
+            _ => return -1,
+        };
+        let mut area = self.areas.remove(&start).unwrap();
+        let area_start = area.vpn_range.get_start();
+        let area_end = area.vpn_range.get_end();
+        let map_type = area.map_type;
+        let map_perm = area.map_perm;
+        // free the pages in the middle; a reserved page that was never faulted
+        // in has no present PTE, so skipping it keeps unmap a no-op rather than
+        // tripping the `is_valid` assert in `PageTable::unmap`.
+        for vpn in VPNRange::new(req_start, req_end) {
+            if area.data_frames.contains_key(&vpn) {
+                area.unmap_one(&mut self.page_table, vpn);
+            }
+        }
+        // left survivor [area_start, req_start)
+        if area_start < req_start {
+            let mut left = MapArea::from_range(area_start, req_start, map_type, map_perm);
+            for vpn in VPNRange::new(area_start, req_start) {
+                if let Some(frame) = area.data_frames.remove(&vpn) {
+                    left.data_frames.insert(vpn, frame);
+                }
+            }
+            self.areas.insert(area_start, left);
+        }
+        // right survivor [req_end, area_end)
+        if req_end < area_end {
+            let mut right = MapArea::from_range(req_end, area_end, map_type, map_perm);
+            for vpn in VPNRange::new(req_end, area_end) {
+                if let Some(frame) = area.data_frames.remove(&vpn) {
+                    right.data_frames.insert(vpn, frame);
+                }
+            }
+            self.areas.insert(req_end, right);
+        }
+        0
+    }
+
+    /// Remove a region by its start VPN, unmapping its pages.
+    pub fn remove_area_with_start_vpn(&mut self, start_vpn: VirtPageNum) {
+        if let Some(mut area) = self.areas.remove(&start_vpn) {
+            area.unmap(&mut self.page_table);
+        }
+    }
+
+    /// Add a region, copying `data` into it when provided.
+    fn push(&mut self, mut map_area: MapArea, data: Option<&[u8]>) {
+        map_area.map(&mut self.page_table);
+        if let Some(data) = data {
+            map_area.copy_data(&mut self.page_table, data);
+        }
+        self.record_area(map_area);
+    }
+
+    /// Mention the trampoline page, which is not collected by areas.
+    fn map_trampoline(&mut self) {
+        self.page_table.map(
+            VirtAddr::from(TRAMPOLINE).into(),
+            PhysAddr::from(strampoline as usize).into(),
+            PTEFlags::R | PTEFlags::X,
+        );
+    }
+
+    /// Without kernel stacks.
+    pub fn new_kernel() -> Self {
+        let mut memory_set = Self::new_bare();
+        // map trampoline
+        memory_set.map_trampoline();
+        // map kernel sections
+        memory_set.push(
+            MapArea::new(
+                (stext as usize).into(),
+                (etext as usize).into(),
+                MapType::Identical,
+                MapPermission::R | MapPermission::X,
+            ),
+            None,
+        );
+        memory_set.push(
+            MapArea::new(
+                (srodata as usize).into(),
+                (erodata as usize).into(),
+                MapType::Identical,
+                MapPermission::R,
+            ),
+            None,
+        );
+        memory_set.push(
+            MapArea::new(
+                (sdata as usize).into(),
+                (edata as usize).into(),
+                MapType::Identical,
+                MapPermission::R | MapPermission::W,
+            ),
+            None,
+        );
+        memory_set.push(
+            MapArea::new(
+                (sbss_with_stack as usize).into(),
+                (ebss as usize).into(),
+                MapType::Identical,
+                MapPermission::R | MapPermission::W,
+            ),
+            None,
+        );
+        memory_set.push(
+            MapArea::new(
+                (ekernel as usize).into(),
+                MEMORY_END.into(),
+                MapType::Identical,
+                MapPermission::R | MapPermission::W,
+            ),
+            None,
+        );
+        memory_set
+    }
+
+    /// Include sections in elf and trampoline and TrapContext and user stack,
+    /// also returns the user stack top (initial user sp) and entry point.
+    pub fn from_elf(elf_data: &[u8]) -> (Self, usize, usize) {
+        let mut memory_set = Self::new_bare();
+        // map trampoline
+        memory_set.map_trampoline();
+        // map program headers of elf, with U flag
+        let elf = xmas_elf::ElfFile::new(elf_data).unwrap();
+        let elf_header = elf.header;
+        let magic = elf_header.pt1.magic;
+        assert_eq!(magic, [0x7f, 0x45, 0x4c, 0x46], "invalid elf!");
+        let ph_count = elf_header.pt2.ph_count();
+        let mut max_end_vpn = VirtPageNum(0);
+        for i in 0..ph_count {
+            let ph = elf.program_header(i).unwrap();
+            if ph.get_type().unwrap() == xmas_elf::program::Type::Load {
+                let start_va: VirtAddr = (ph.virtual_addr() as usize).into();
+                let end_va: VirtAddr = ((ph.virtual_addr() + ph.mem_size()) as usize).into();
+                let mut map_perm = MapPermission::U;
+                let ph_flags = ph.flags();
+                if ph_flags.is_read() {
+                    map_perm |= MapPermission::R;
+                }
+                if ph_flags.is_write() {
+                    map_perm |= MapPermission::W;
+                }
+                if ph_flags.is_execute() {
+                    map_perm |= MapPermission::X;
+                }
+                let map_area = MapArea::new(start_va, end_va, MapType::Framed, map_perm);
+                max_end_vpn = map_area.vpn_range.get_end();
+                memory_set.push(
+                    map_area,
+                    Some(&elf.input[ph.offset() as usize..(ph.offset() + ph.file_size()) as usize]),
+                );
+            }
+        }
+        // map user stack with U flags
+        let max_end_va: VirtAddr = max_end_vpn.into();
+        let mut user_stack_base: usize = max_end_va.into();
+        user_stack_base += PAGE_SIZE;
+        let user_stack_top = user_stack_base + USER_STACK_SIZE;
+        let urw = MapPermission::R | MapPermission::W | MapPermission::U;
+        memory_set.push(MapArea::new(user_stack_base.into(), user_stack_top.into(), MapType::Framed, urw), None);
+        // empty heap region at the stack top, grown and shrunk by sbrk
+        memory_set.push(MapArea::new(user_stack_top.into(), user_stack_top.into(), MapType::Framed, urw), None);
+        // map TrapContext
+        let trap_perm = MapPermission::R | MapPermission::W;
+        memory_set.push(MapArea::new(TRAP_CONTEXT_BASE.into(), TRAMPOLINE.into(), MapType::Framed, trap_perm), None);
+        (
+            memory_set,
+            user_stack_top,
+            elf.header.pt2.entry_point() as usize,
+        )
+    }
+
+    /// Activate this address space on the current hart.
+    pub fn activate(&self) {
+        let satp = self.page_table.token();
+        unsafe {
+            satp::write(satp);
+            core::arch::asm!("sfence.vma");
+        }
+    }
+
+    /// Translate a vpn to a pte copy.
+    pub fn translate(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
+        self.page_table.translate(vpn)
+    }
+
+    /// Shrink the heap region (start key) down to `new_end`.
+    pub fn shrink_to(&mut self, start: VirtAddr, new_end: VirtAddr) -> bool {
+        if let Some(area) = self.areas.get_mut(&start.floor()) {
+            area.shrink(&mut self.page_table, new_end.ceil());
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Grow the heap region (start key) up to `new_end`.
+    pub fn append_to(&mut self, start: VirtAddr, new_end: VirtAddr) -> bool {
+        if let Some(area) = self.areas.get_mut(&start.floor()) {
+            area.append(&mut self.page_table, new_end.ceil());
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Drop all the user-space regions, freeing their frames.
+    pub fn recycle_data_pages(&mut self) {
+        self.areas.clear();
+    }
+}
+
+/// A contiguous, permission-homogeneous virtual region (a `vm_area_struct`).
+pub struct MapArea {
+    vpn_range: VPNRange,
+    data_frames: BTreeMap<VirtPageNum, FrameTracker>,
+    map_type: MapType,
+    map_perm: MapPermission,
+}
+
+impl MapArea {
+    /// Create a region spanning `[start_va, end_va)`.
+    pub fn new(
+        start_va: VirtAddr,
+        end_va: VirtAddr,
+        map_type: MapType,
+        map_perm: MapPermission,
+    ) -> Self {
+        let start_vpn: VirtPageNum = start_va.floor();
+        let end_vpn: VirtPageNum = end_va.ceil();
+        Self {
+            vpn_range: VPNRange::new(start_vpn, end_vpn),
+            data_frames: BTreeMap::new(),
+            map_type,
+            map_perm,
+        }
+    }
+    /// Create an empty region over `[start, end)` inheriting type/permission.
+    pub fn from_range(
+        start: VirtPageNum,
+        end: VirtPageNum,
+        map_type: MapType,
+        map_perm: MapPermission,
+    ) -> Self {
+        Self {
+            vpn_range: VPNRange::new(start, end),
+            data_frames: BTreeMap::new(),
+            map_type,
+            map_perm,
+        }
+    }
+    /// Map a single page into the page table.
+    pub fn map_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
+        let ppn: PhysPageNum;
+        match self.map_type {
+            MapType::Identical => {
+                ppn = PhysPageNum(vpn.0);
+            }
+            MapType::Framed => {
+                let frame = frame_alloc().unwrap();
+                ppn = frame.ppn;
+                self.data_frames.insert(vpn, frame);
+            }
+        }
+        let pte_flags = PTEFlags::from_bits(self.map_perm.bits()).unwrap();
+        page_table.map(vpn, ppn, pte_flags);
+    }
+    /// Unmap a single page, freeing its frame for framed regions.
+    pub fn unmap_one(&mut self, page_table: &mut PageTable, vpn: VirtPageNum) {
+        if self.map_type == MapType::Framed {
+            self.data_frames.remove(&vpn);
+        }
+        page_table.unmap(vpn);
+    }
+    /// Map every page in the region.
+    pub fn map(&mut self, page_table: &mut PageTable) {
+        for vpn in self.vpn_range {
+            self.map_one(page_table, vpn);
+        }
+    }
+    /// Unmap every page in the region.
+    pub fn unmap(&mut self, page_table: &mut PageTable) {
+        for vpn in self.vpn_range {
+            self.unmap_one(page_table, vpn);
+        }
+    }
+    /// Shrink the region down to `new_end`, unmapping the tail.
+    pub fn shrink(&mut self, page_table: &mut PageTable, new_end: VirtPageNum) {
+        for vpn in VPNRange::new(new_end, self.vpn_range.get_end()) {
+            self.unmap_one(page_table, vpn);
+        }
+        self.vpn_range = VPNRange::new(self.vpn_range.get_start(), new_end);
+    }
+    /// Grow the region up to `new_end`, mapping the new tail.
+    pub fn append(&mut self, page_table: &mut PageTable, new_end: VirtPageNum) {
+        for vpn in VPNRange::new(self.vpn_range.get_end(), new_end) {
+            self.map_one(page_table, vpn);
+        }
+        self.vpn_range = VPNRange::new(self.vpn_range.get_start(), new_end);
+    }
+    /// Copy `data` into the region, page by page, from its start.
+    pub fn copy_data(&mut self, page_table: &mut PageTable, data: &[u8]) {
+        assert_eq!(self.map_type, MapType::Framed);
+        let mut start: usize = 0;
+        let mut current_vpn = self.vpn_range.get_start();
+        let len = data.len();
+        loop {
+            let src = &data[start..len.min(start + PAGE_SIZE)];
+            let dst = &mut page_table
+                .translate(current_vpn)
+                .unwrap()
+                .ppn()
+                .get_bytes_array()[..src.len()];
+            dst.copy_from_slice(src);
+            start += PAGE_SIZE;
+            if start >= len {
+                break;
+            }
+            current_vpn.step();
+        }
+    }
+}
+
+/// How a region is backed.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum MapType {
+    /// Identity-mapped (kernel).
+    Identical,
+    /// Backed by freshly allocated frames.
+    Framed,
+}
+
+bitflags! {
+    /// Map permission corresponding to the bits in the page table entry.
+    pub struct MapPermission: u8 {
+        /// Readable
+        const R = 1 << 1;
+        /// Writable
+        const W = 1 << 2;
+        /// Executable
+        const X = 1 << 3;
+        /// Accessible in U mode
+        const U = 1 << 4;
+    }
+}
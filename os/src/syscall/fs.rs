@@ -0,0 +1,28 @@
+//! File and filesystem-related syscalls
+use crate::mm::translated_byte_buffer;
+use crate::task::current_user_token;
+
+const FD_STDOUT: usize = 1;
+
+/// write buf of length `len` to a file with `fd`
+pub fn sys_write(fd: usize, buf: *const u8, len: usize) -> isize {
+    trace!("kernel: sys_write");
+    match fd {
+        FD_STDOUT => {
+            let buffers = translated_byte_buffer(current_user_token(), buf, len);
+            for buffer in buffers {
+                print!("{}", core::str::from_utf8(buffer).unwrap());
+            }
+            len as isize
+        }
+        _ => {
+            panic!("Unsupported fd in sys_write!");
+        }
+    }
+}
+
+/// read from a file with `fd`; reading is not supported, so this always returns -1
+pub fn sys_read(_fd: usize, _buf: *const u8, _len: usize) -> isize {
+    trace!("kernel: sys_read");
+    -1
+}
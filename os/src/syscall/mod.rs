@@ -0,0 +1,62 @@
+//! Implementation of syscalls
+//!
+//! The single entry point to all system calls, [`syscall()`], is called
+//! whenever userspace wishes to perform a system call using the `ecall`
+//! instruction. In this case, the processor raises an 'Environment call from
+//! U-mode' exception, which is handled as one of the cases in
+//! [`crate::trap::trap_handler`].
+
+/// read syscall
+const SYSCALL_READ: usize = 63;
+/// write syscall
+const SYSCALL_WRITE: usize = 64;
+/// exit syscall
+const SYSCALL_EXIT: usize = 93;
+/// yield syscall
+const SYSCALL_YIELD: usize = 124;
+/// setpriority syscall
+const SYSCALL_SET_PRIORITY: usize = 140;
+/// gettime syscall
+const SYSCALL_GET_TIME: usize = 169;
+/// sbrk syscall
+const SYSCALL_SBRK: usize = 214;
+/// munmap syscall
+const SYSCALL_MUNMAP: usize = 215;
+/// mmap syscall
+const SYSCALL_MMAP: usize = 222;
+/// spawn syscall
+const SYSCALL_SPAWN: usize = 400;
+/// taskinfo syscall
+const SYSCALL_TASK_INFO: usize = 410;
+
+mod fs;
+mod process;
+
+use crate::config::MAX_SYSCALL_NUM;
+use crate::task::inc_sys_call_time;
+use fs::*;
+use process::*;
+
+/// handle syscall exception with `syscall_id` and other arguments
+pub fn syscall(syscall_id: usize, args: [usize; 3]) -> isize {
+    // Only syscall numbers we actually track fit in the per-task counter array;
+    // guard the index so an out-of-range id falls through to the panic below
+    // instead of writing past the end of `task_sys_calls`.
+    if syscall_id < MAX_SYSCALL_NUM {
+        inc_sys_call_time(syscall_id);
+    }
+    match syscall_id {
+        SYSCALL_READ => sys_read(args[0], args[1] as *const u8, args[2]),
+        SYSCALL_WRITE => sys_write(args[0], args[1] as *const u8, args[2]),
+        SYSCALL_EXIT => sys_exit(args[0] as i32),
+        SYSCALL_YIELD => sys_yield(),
+        SYSCALL_SET_PRIORITY => sys_set_priority(args[0] as isize),
+        SYSCALL_GET_TIME => sys_get_time(args[0] as *mut TimeVal, args[1]),
+        SYSCALL_SBRK => sys_sbrk(args[0] as i32),
+        SYSCALL_MUNMAP => sys_munmap(args[0], args[1]),
+        SYSCALL_MMAP => sys_mmap(args[0], args[1], args[2]),
+        SYSCALL_SPAWN => sys_spawn(args[0] as *const u8),
+        SYSCALL_TASK_INFO => sys_task_info(args[0] as *mut TaskInfo),
+        _ => panic!("Unsupported syscall_id: {}", syscall_id),
+    }
+}
@@ -3,8 +3,8 @@
 use core::mem::size_of;
 
 use crate::{
-    config::{MAX_SYSCALL_NUM, PAGE_SIZE}, mm::{translated_byte_buffer, MapPermission, VPNRange, VirtAddr}, task::{
-        change_program_brk, current_user_token, exit_current_and_run_next, suspend_current_and_run_next, TaskStatus, TASK_MANAGER
+    config::{MAX_SYSCALL_NUM, PAGE_SIZE}, loader::get_app_data_by_name, mm::{translated_byte_buffer, translated_str, MapPermission, VirtAddr}, task::{
+        add_new_mem_area, change_program_brk, current_user_token, exit_current_and_run_next, set_current_task_priority, spawn_current, suspend_current_and_run_next, unmap_mem_area, TaskStatus, TASK_MANAGER
     }, timer::{get_time_ms, get_time_us}
 };
 
@@ -98,27 +98,18 @@ pub fn sys_task_info(_ti: *mut TaskInfo) -> isize {
 // YOUR JOB: Implement mmap.
 pub fn sys_mmap(_start: usize, _len: usize, _port: usize) -> isize {
     trace!("kernel: sys_mmap NOT IMPLEMENTED YET!");
-    if _start & (PAGE_SIZE - 1) != 0 
+    if _start & (PAGE_SIZE - 1) != 0
     || _port & !0x7 != 0
     || _port & 0x7 == 0 {
         return -1;
     }
     let vpn_st = VirtAddr::from(_start).floor();
     let vpn_ed= VirtAddr::from(_start + _len).ceil();
-    let vpn_range = VPNRange::new(vpn_st, vpn_ed);
-    //检查要映射的虚拟地址空间，如果已经有被分配过的，返回-1表示失败
-    for vpn in vpn_range {
-        if let Some(pte) = TASK_MANAGER.get_page_table_entry(vpn) {
-            if pte.is_valid() {
-                return -1;
-            }
-        }
-    }
-    TASK_MANAGER.add_new_mem_area(
-        vpn_st.into(), 
-        vpn_ed.into(), 
-        MapPermission::from_bits_truncate((_port << 1) as u8) | MapPermission::U);
-    0
+    //重叠检查已下沉到按起始 VPN 排序的区间树，登记区域时在 O(log n) 内拒绝重叠。
+    add_new_mem_area(
+        vpn_st.into(),
+        vpn_ed.into(),
+        MapPermission::from_bits_truncate((_port << 1) as u8) | MapPermission::U)
 }
 
 // YOUR JOB: Implement munmap.
@@ -127,8 +118,33 @@ pub fn sys_munmap(_start: usize, _len: usize) -> isize {
     if _start &(PAGE_SIZE - 1) != 0 {
         return -1;
     }
-    TASK_MANAGER.unmap_mem_area(_start, _len)
+    unmap_mem_area(_start, _len)
+}
+/// YOUR JOB: Set the priority of the current task for stride scheduling.
+/// 优先级必须不小于 2，否则 pass = BIG_STRIDE / priority 会失真，返回 -1。
+pub fn sys_set_priority(_prio: isize) -> isize {
+    trace!("kernel: sys_set_priority");
+    if _prio < 2 {
+        return -1;
+    }
+    set_current_task_priority(_prio as usize);
+    _prio
 }
+
+/// Create a child process directly from an app image, skipping the fork-then-exec
+/// detour so the parent's address space is never copied. Returns the new pid, or
+/// -1 if the app name is unknown.
+pub fn sys_spawn(_path: *const u8) -> isize {
+    trace!("kernel: sys_spawn");
+    let token = current_user_token();
+    let path = translated_str(token, _path);
+    if let Some(data) = get_app_data_by_name(path.as_str()) {
+        spawn_current(data)
+    } else {
+        -1
+    }
+}
+
 /// change data segment size
 pub fn sys_sbrk(size: i32) -> isize {
     trace!("kernel: sys_sbrk");
@@ -0,0 +1,33 @@
+//! Implementation of [`TaskContext`]
+use crate::trap::trap_return;
+
+/// Task Context saved and restored by `__switch`.
+#[derive(Copy, Clone)]
+#[repr(C)]
+pub struct TaskContext {
+    /// return address ( e.g. __restore ) of __switch ASM function
+    ra: usize,
+    /// kernel stack pointer of app
+    sp: usize,
+    /// callee-saved registers:  s 0..11
+    s: [usize; 12],
+}
+
+impl TaskContext {
+    /// Create a new empty task context
+    pub fn zero_init() -> Self {
+        Self {
+            ra: 0,
+            sp: 0,
+            s: [0; 12],
+        }
+    }
+    /// Create a new task context with a trap return addr and a kernel stack pointer
+    pub fn goto_trap_return(kstack_ptr: usize) -> Self {
+        Self {
+            ra: trap_return as usize,
+            sp: kstack_ptr,
+            s: [0; 12],
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Implementation of [`TaskManager`] and its pluggable ready-queue scheduler.
+//!
+//! `TaskManager` no longer hard-codes a FIFO `VecDeque`; it owns a boxed
+//! [`Scheduler`], so the scheduling policy can be swapped out. [`FifoScheduler`]
+//! reproduces the original first-in-first-out behaviour, while
+//! [`StrideScheduler`] implements stride scheduling on top of the same trait.
+//!
+//! The abstraction mirrors the pluggable scheduler in the tornado-os sources.
+use super::{current_task, TaskControlBlock, TaskStatus};
+use crate::config::MAX_SYSCALL_NUM;
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::sync::Arc;
+use lazy_static::*;
+use spin::Mutex;
+
+/// The largest stride step. Each task advances by `pass = BIG_STRIDE / priority`
+/// every time it is scheduled; since priorities are kept `>= 2`, every
+/// `pass <= BIG_STRIDE / 2`, which keeps the live spread of strides below half
+/// of `u64`'s range so the wrapping comparison in [`stride_lt`] stays correct.
+pub const BIG_STRIDE: u64 = 0x10000;
+
+/// `a < b` under wrapping stride arithmetic: when `a.wrapping_sub(b)` has its
+/// high bit set, `a` is "behind" `b`. Correct as long as the spread stays under
+/// half of the range, which `pass <= BIG_STRIDE` guarantees.
+fn stride_lt(a: u64, b: u64) -> bool {
+    a.wrapping_sub(b) & (1 << 63) != 0
+}
+
+/// A pluggable ready-queue policy over schedulable items `T`.
+pub trait Scheduler<T> {
+    /// Add a runnable item to the queue.
+    fn insert(&mut self, task: T);
+    /// Remove and return the item that should run next, if any.
+    fn pop(&mut self) -> Option<T>;
+    /// Borrow the next-to-run item without removing it.
+    fn peek_mut(&mut self) -> Option<&mut T>;
+    /// Remove a specific item from the queue, returning it if present.
+    fn remove(&mut self, task: &T) -> Option<T>;
+}
+
+/// Plain first-in-first-out scheduler (the original behaviour).
+pub struct FifoScheduler {
+    ready_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl FifoScheduler {
+    pub fn new() -> Self {
+        Self {
+            ready_queue: VecDeque::new(),
+        }
+    }
+}
+
+impl Scheduler<Arc<TaskControlBlock>> for FifoScheduler {
+    fn insert(&mut self, task: Arc<TaskControlBlock>) {
+        self.ready_queue.push_back(task);
+    }
+    fn pop(&mut self) -> Option<Arc<TaskControlBlock>> {
+        self.ready_queue.pop_front()
+    }
+    fn peek_mut(&mut self) -> Option<&mut Arc<TaskControlBlock>> {
+        self.ready_queue.front_mut()
+    }
+    fn remove(&mut self, task: &Arc<TaskControlBlock>) -> Option<Arc<TaskControlBlock>> {
+        let pos = self.ready_queue.iter().position(|t| Arc::ptr_eq(t, task))?;
+        self.ready_queue.remove(pos)
+    }
+}
+
+/// Stride scheduler: picks the runnable task with the smallest `stride`, then
+/// advances that task's stride by its `pass` just before it runs.
+pub struct StrideScheduler {
+    ready_queue: VecDeque<Arc<TaskControlBlock>>,
+}
+
+impl StrideScheduler {
+    pub fn new() -> Self {
+        Self {
+            ready_queue: VecDeque::new(),
+        }
+    }
+
+    /// Index of the queued task with the smallest stride, using wrapping order.
+    fn smallest_stride(&self) -> Option<usize> {
+        let mut best: Option<usize> = None;
+        for (i, task) in self.ready_queue.iter().enumerate() {
+            let stride = task.inner_exclusive_access().stride;
+            match best {
+                None => best = Some(i),
+                Some(bi) => {
+                    let best_stride = self.ready_queue[bi].inner_exclusive_access().stride;
+                    if stride_lt(stride, best_stride) {
+                        best = Some(i);
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
+impl Scheduler<Arc<TaskControlBlock>> for StrideScheduler {
+    fn insert(&mut self, task: Arc<TaskControlBlock>) {
+        self.ready_queue.push_back(task);
+    }
+    fn pop(&mut self) -> Option<Arc<TaskControlBlock>> {
+        let idx = self.smallest_stride()?;
+        let task = self.ready_queue.remove(idx).unwrap();
+        // advance the stride by `pass` right before handing the task out to run
+        {
+            let mut inner = task.inner_exclusive_access();
+            let pass = inner.pass();
+            inner.stride = inner.stride.wrapping_add(pass);
+        }
+        Some(task)
+    }
+    fn peek_mut(&mut self) -> Option<&mut Arc<TaskControlBlock>> {
+        let idx = self.smallest_stride()?;
+        self.ready_queue.get_mut(idx)
+    }
+    fn remove(&mut self, task: &Arc<TaskControlBlock>) -> Option<Arc<TaskControlBlock>> {
+        let pos = self.ready_queue.iter().position(|t| Arc::ptr_eq(t, task))?;
+        self.ready_queue.remove(pos)
+    }
+}
+
+/// `TaskManager` manages all the runnable tasks behind a boxed [`Scheduler`].
+///
+/// The ready queue is guarded by a [`spin::Mutex`] rather than `UPSafeCell`, so
+/// multiple harts entering `run_tasks` → `fetch_task` concurrently serialise on
+/// the lock instead of panicking on overlapping `exclusive_access`.
+pub struct TaskManager {
+    scheduler: Mutex<Box<dyn Scheduler<Arc<TaskControlBlock>> + Send>>,
+}
+
+impl TaskManager {
+    /// Create a manager using the stride scheduler.
+    pub fn new() -> Self {
+        Self {
+            scheduler: Mutex::new(Box::new(StrideScheduler::new())),
+        }
+    }
+    /// Add a task to the ready queue.
+    pub fn add(&self, task: Arc<TaskControlBlock>) {
+        self.scheduler.lock().insert(task);
+    }
+    /// Take the next task to run out of the ready queue.
+    pub fn fetch(&self) -> Option<Arc<TaskControlBlock>> {
+        self.scheduler.lock().pop()
+    }
+
+    ///get current task's status
+    pub fn get_current_task_status(&self) -> TaskStatus {
+        current_task().unwrap().inner_exclusive_access().task_status
+    }
+    ///获取当前任务的系统调用情况
+    pub fn get_current_task_sys_calls(&self) -> [u32; MAX_SYSCALL_NUM] {
+        current_task()
+            .unwrap()
+            .inner_exclusive_access()
+            .task_sys_calls
+    }
+    ///获取当前任务的开始调度时间
+    pub fn get_current_task_start(&self) -> usize {
+        current_task().unwrap().inner_exclusive_access().task_start
+    }
+}
+
+lazy_static! {
+    /// The global task manager holding the shared ready queue.
+    pub static ref TASK_MANAGER: TaskManager = TaskManager::new();
+}
+
+/// Add a task to the global ready queue.
+pub fn add_task(task: Arc<TaskControlBlock>) {
+    TASK_MANAGER.add(task);
+}
+
+/// Fetch the next runnable task from the global ready queue.
+pub fn fetch_task() -> Option<Arc<TaskControlBlock>> {
+    TASK_MANAGER.fetch()
+}
@@ -6,8 +6,8 @@
 //! A single global instance of [`TaskManager`] called `TASK_MANAGER` controls
 //! all the tasks in the whole operating system.
 //!
-//! A single global instance of [`Processor`] called `PROCESSOR` monitors running
-//! task(s) for each core.
+//! A per-hart array of [`Processor`] instances called `PROCESSORS`, indexed by
+//! hart id, monitors the task running on each core.
 //!
 //! A single global instance of `PID_ALLOCATOR` allocates pid for user apps.
 //!
@@ -32,9 +32,8 @@ pub use context::TaskContext;
 pub use id::{kstack_alloc, pid_alloc, KernelStack, PidHandle};
 pub use manager::add_task;
 pub use processor::{
-    current_task, current_trap_cx, current_user_token, run_tasks, schedule, take_current_task,
-    Processor,
-    PROCESSOR,
+    current_task, current_trap_cx, current_user_token, hartid, run_tasks, schedule,
+    take_current_task, Processor, PROCESSORS,
 };
 /// Suspend the current 'Running' task and run the next task in task list.
 pub fn suspend_current_and_run_next() {
@@ -131,6 +130,19 @@ pub fn get_current_task_start() -> usize {
     task_inner.task_start 
 }
 
+///修改当前任务的 program break，成功返回旧的 break，失败返回 None
+pub fn change_program_brk(size: i32) -> Option<usize> {
+    let task = current_task().unwrap();
+    task.change_program_brk(size)
+}
+
+///设置当前任务的优先级，stride 调度器据此计算 pass = BIG_STRIDE / priority
+pub fn set_current_task_priority(prio: usize) {
+    let task = current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    task_inner.priority = prio;
+}
+
 ///获取当前应用的页表
 pub fn get_page_table_entry(vpn: VirtPageNum) -> Option<PageTableEntry> {
     let task = current_task().unwrap();
@@ -138,30 +150,50 @@ pub fn get_page_table_entry(vpn: VirtPageNum) -> Option<PageTableEntry> {
     task_inner.memory_set.translate(vpn) 
 }
 
-///将给出的虚拟地址空间加入到地址空间中，自然实现了虚拟地址向物理地址的映射
-pub fn add_new_mem_area(start_va: VirtAddr ,end_va: VirtAddr, perm: MapPermission) {
+///将给出的虚拟地址空间加入到地址空间中，自然实现了虚拟地址向物理地址的映射。
+///区域被登记到按起始 VPN 排序的 `BTreeMap`，重叠检查在 O(log n) 内完成，重叠则返回 -1。
+pub fn add_new_mem_area(start_va: VirtAddr ,end_va: VirtAddr, perm: MapPermission) -> isize {
     let task = current_task().unwrap();
     let mut task_inner = task.inner_exclusive_access();
-    task_inner.memory_set.insert_framed_area(start_va, end_va, perm);
+    //惰性 mmap：只在区间树中登记区域，不创建 PTE，也不在此处分配物理帧。
+    task_inner.memory_set.reserve_framed_area(start_va, end_va, perm)
 }
 
-///将给出的虚拟地址范围从应用地址空间中删除映射关系
+///缺页处理：若 `va` 落在某个已保留但尚未调入的 mmap 区域内，为该页分配一个物理帧、
+///按区域的 `MapPermission` 映射、清零后返回 true，调用方据此重试出错指令；
+///若 `va` 不属于任何保留区域则返回 false，调用方据此杀死任务。
+pub fn handle_mmap_page_fault(va: VirtAddr) -> bool {
+    let task = current_task().unwrap();
+    let mut task_inner = task.inner_exclusive_access();
+    task_inner.memory_set.fault_in(va)
+}
+
+///将给出的虚拟地址范围从应用地址空间中删除映射关系。
+///定位覆盖 `[start, len)` 的区域：若请求范围严格落在区域内部，则把它拆分成至多两个存活区域，
+///只释放中间的页；否则释放整段。找不到覆盖区域返回 -1。
 pub fn unmap_mem_area(start: usize, len: usize) -> isize{
-    //没有给出直接删除一段的函数，那么只能一个一个unmap
     let task = current_task().unwrap();
     let mut task_inner = task.inner_exclusive_access();
     let vpn_st = VirtAddr::from(start).floor();
     let vpn_ed = VirtAddr::from(start + len).ceil();
-    let vpn_range = VPNRange::new(vpn_st, vpn_ed);
-    for vpn in vpn_range {
-        if let Some(pte) = task_inner.memory_set.translate(vpn) {
-            if !pte.is_valid() {
-                return -1;
-            }
-            task_inner.memory_set.erase_virt_map(vpn);
-        }
+    task_inner.memory_set.split_and_unmap(VPNRange::new(vpn_st, vpn_ed))
+}
+
+///直接由应用镜像创建一个子进程：解析出全新的 `MemorySet`、trap 上下文、内核栈与 pid，
+///挂到当前任务的子进程链表并加入调度器，返回新进程 pid。
+///相比 fork 再 exec，省去了复制并立即丢弃父进程整个地址空间的开销。
+pub fn spawn_current(data: &[u8]) -> isize {
+    let current = current_task().unwrap();
+    let new_task = Arc::new(TaskControlBlock::new(data));
+    // 建立父子关系
+    {
+        let mut new_inner = new_task.inner_exclusive_access();
+        new_inner.parent = Some(Arc::downgrade(&current));
     }
-    0
+    current.inner_exclusive_access().children.push(new_task.clone());
+    let pid = new_task.getpid() as isize;
+    add_task(new_task);
+    pid
 }
 
 lazy_static! {
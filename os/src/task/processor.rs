@@ -7,8 +7,7 @@
 use super::__switch;
 use super::{fetch_task, TaskStatus};
 use super::{TaskContext, TaskControlBlock};
-use crate::config::MAX_SYSCALL_NUM;
-use crate::mm::{MapPermission, PageTableEntry, VPNRange, VirtAddr, VirtPageNum};
+use crate::config::MAX_HARTS;
 use crate::sync::UPSafeCell;
 use crate::timer::get_time_ms;
 use crate::trap::TrapContext;
@@ -47,72 +46,41 @@ impl Processor {
     pub fn current(&self) -> Option<Arc<TaskControlBlock>> {
         self.current.as_ref().map(Arc::clone)
     }
+}
 
-    ///get current task's status
-    pub fn get_current_task_status(&self) -> TaskStatus {
-        let task_inner = self.current.as_ref().unwrap().inner_exclusive_access();
-        task_inner.task_status
-    }
-
-    ///increase current tasks's syscall times
-    pub fn inc_sys_call_time(&mut self, syscall_id: usize) {
-       let mut task_inner = self.current.as_mut().unwrap().inner_exclusive_access(); 
-       task_inner.task_sys_calls[syscall_id] += 1;
-    }
-
-    ///获取当前任务的系统调用情况
-    pub fn get_current_task_sys_calls(&self) -> [u32;MAX_SYSCALL_NUM] {
-        let task_inner = self.current.as_ref().unwrap().inner_exclusive_access();
-        task_inner.task_sys_calls
-    }
-
-    ///或者当前任务的开始调度时间
-    pub fn get_current_task_start(&self) -> usize {
-       let task_inner = self.current.as_ref().unwrap().inner_exclusive_access();
-       task_inner.task_start 
-    }
-
-    ///获取当前应用的页表
-    pub fn get_page_table_entry(&self, vpn: VirtPageNum) -> Option<PageTableEntry> {
-       let task_inner = self.current.as_ref().unwrap().inner_exclusive_access();
-       task_inner.memory_set.translate(vpn) 
-    }
-
-    ///将给出的虚拟地址空间加入到地址空间中，自然实现了虚拟地址向物理地址的映射
-    pub fn add_new_mem_area(&mut self, start_va: VirtAddr ,end_va: VirtAddr, perm: MapPermission) {
-       let mut task_inner = self.current.as_mut().unwrap().inner_exclusive_access();
-       task_inner.memory_set.insert_framed_area(start_va, end_va, perm);
-    }
+lazy_static! {
+    /// One [`Processor`] per hart, indexed by hart id. Each core pulls from the
+    /// shared `TASK_MANAGER` but spins on its own idle context.
+    pub static ref PROCESSORS: [UPSafeCell<Processor>; MAX_HARTS] =
+        core::array::from_fn(|_| unsafe { UPSafeCell::new(Processor::new()) });
+}
 
-    ///将给出的虚拟地址范围从应用地址空间中删除映射关系
-    pub fn unmap_mem_area(&mut self, start: usize, len: usize) -> isize{
-        //没有给出直接删除一段的函数，那么只能一个一个unmap
-        let mut task_inner = self.current.as_mut().unwrap().inner_exclusive_access();
-        let vpn_st = VirtAddr::from(start).floor();
-        let vpn_ed = VirtAddr::from(start + len).ceil();
-        let vpn_range = VPNRange::new(vpn_st, vpn_ed);
-        for vpn in vpn_range {
-            if let Some(pte) = task_inner.memory_set.translate(vpn) {
-                if !pte.is_valid() {
-                    return -1;
-                }
-                task_inner.memory_set.erase_virt_map(vpn);
-            }
-        }
-        0
-    }
+/// Hart id of the caller. Every per-hart accessor below routes through this so
+/// that bringing up more harts only needs this one function to become real.
+///
+/// The boot path in this tree starts a single hart and never seeds `tp` with
+/// the SBI-provided hart id, nor is there a secondary-hart entry. Rather than
+/// read an uninitialized `tp` and index `PROCESSORS` out of an unrelated
+/// register, we return 0 until that boot work lands. The `PROCESSORS` array is
+/// kept sized at `MAX_HARTS` so the switch to a real `mv {}, tp` read is local
+/// to this function.
+pub fn hartid() -> usize {
+    0
 }
 
-lazy_static! {
-    /// Manage current task's ControlBlock
-    pub static ref PROCESSOR: UPSafeCell<Processor> = unsafe { UPSafeCell::new(Processor::new()) };
+/// Borrow the [`Processor`] owned by the current hart.
+fn current_processor() -> &'static UPSafeCell<Processor> {
+    &PROCESSORS[hartid()]
 }
 
 ///The main part of process execution and scheduling
 ///Loop `fetch_task` to get the process that needs to run, and switch the process through `__switch`
+///
+///Drives the idle loop for the calling hart; with the current single-hart
+///boot this is hart 0 only (see [`hartid`]).
 pub fn run_tasks() {
     loop {
-        let mut processor = PROCESSOR.exclusive_access();
+        let mut processor = current_processor().exclusive_access();
         if let Some(task) = fetch_task() {
             let idle_task_cx_ptr = processor.get_idle_task_cx_ptr();
             // access coming task TCB exclusively
@@ -134,19 +102,25 @@ pub fn run_tasks() {
                 __switch(idle_task_cx_ptr, next_task_cx_ptr);
             }
         } else {
-            warn!("no tasks available in run_tasks");
+            // No runnable task right now: release the processor and idle the
+            // hart until the next interrupt rather than busy-spinning (and
+            // flooding the log). A timer/IPI wakes us to retry `fetch_task`.
+            drop(processor);
+            unsafe {
+                riscv::asm::wfi();
+            }
         }
     }
 }
 
 /// Get current task through take, leaving a None in its place
 pub fn take_current_task() -> Option<Arc<TaskControlBlock>> {
-    PROCESSOR.exclusive_access().take_current()
+    current_processor().exclusive_access().take_current()
 }
 
 /// Get a copy of the current task
 pub fn current_task() -> Option<Arc<TaskControlBlock>> {
-    PROCESSOR.exclusive_access().current()
+    current_processor().exclusive_access().current()
 }
 
 /// Get the current user token(addr of page table)
@@ -165,7 +139,7 @@ pub fn current_trap_cx() -> &'static mut TrapContext {
 
 ///Return to idle control flow for new scheduling
 pub fn schedule(switched_task_cx_ptr: *mut TaskContext) {
-    let mut processor = PROCESSOR.exclusive_access();
+    let mut processor = current_processor().exclusive_access();
     let idle_task_cx_ptr = processor.get_idle_task_cx_ptr();
     drop(processor);
     unsafe {